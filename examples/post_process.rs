@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::sprite::Material2d;
+use bevy_pixel::texture::{PixelPostProcessMaterial, TexturePixelCamera};
+use bevy_pixel::{prelude::*, plugin::PixelPlugin};
+
+/// A scanline post-process material, run on the upscale quad instead of the default
+/// `ColorMaterial`.
+#[derive(AsBindGroup, TypeUuid, Clone)]
+#[uuid = "b3b0b6d0-4f3a-4f0a-9b8e-1f6b0a8c9d3e"]
+struct ScanlineMaterial {
+    #[texture(0)]
+    #[sampler(1)]
+    texture: Handle<Image>,
+    #[uniform(2)]
+    canvas_size: Vec2,
+}
+
+impl Material2d for ScanlineMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/scanlines.wgsl".into()
+    }
+}
+
+impl PixelPostProcessMaterial for ScanlineMaterial {
+    fn from_canvas(texture: Handle<Image>, canvas_size: Vec2) -> Self {
+        Self {
+            texture,
+            canvas_size,
+        }
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(PixelPlugin::<ScanlineMaterial>::default())
+        .add_startup_system(setup)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((Camera2dBundle::default(), TexturePixelCamera::default()));
+    commands.spawn(SpriteBundle {
+        texture: asset_server.load("tile_0006.png"),
+        ..Default::default()
+    });
+}
@@ -1,10 +1,10 @@
 use bevy::prelude::*;
-use bevy_pixel::{prelude::*, texture::TexturePixelCamera};
+use bevy_pixel::{plugin::PixelPlugin, prelude::*, texture::TexturePixelCamera};
 
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
-        .add_plugin(PixelPlugin)
+        .add_plugin(PixelPlugin::<ColorMaterial>::default())
         .add_startup_system(setup)
         .run();
 }
@@ -0,0 +1,69 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::sprite::Material2dPlugin;
+use bevy::window::WindowResized;
+use bevy::winit::{UpdateMode, WinitSettings};
+
+use crate::texture::{scale_render_image, setup_camera, PixelPostProcessMaterial};
+
+/// Drives [`TexturePixelCamera`](crate::texture::TexturePixelCamera) rendering:
+/// allocates its render texture and upscale quad on spawn, and keeps the final
+/// viewport fitted to the window every frame.
+///
+/// Generic over the [`PixelPostProcessMaterial`] drawn on the upscale quad, defaulting
+/// to plain `ColorMaterial`. Supply a custom material to run a CRT, scanline, or
+/// palette-quantization shader between the virtual canvas and the final camera, e.g.
+/// `PixelPlugin::<MyPostProcessMaterial>::default()`. See `examples/post_process.rs`.
+pub struct PixelPlugin<M: PixelPostProcessMaterial = ColorMaterial> {
+    /// When `true`, configures winit for reactive rendering
+    /// (`WinitSettings::desktop_app()`-style: only redraw on window/input events)
+    /// instead of continuous rendering, cutting CPU/GPU usage for mostly-static
+    /// pixel-art scenes. Bevy presents nothing on the frame a window is resized, so a
+    /// redraw is still forced for one extra frame after any resize so
+    /// `scale_render_image` can re-fit the viewport.
+    pub reactive: bool,
+    _material: PhantomData<M>,
+}
+
+impl<M: PixelPostProcessMaterial> Default for PixelPlugin<M> {
+    fn default() -> Self {
+        Self {
+            reactive: false,
+            _material: PhantomData,
+        }
+    }
+}
+
+impl<M: PixelPostProcessMaterial> Plugin for PixelPlugin<M> {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<Material2dPlugin<M>>() {
+            app.add_plugin(Material2dPlugin::<M>::default());
+        }
+
+        app.add_system(setup_camera::<M>).add_system(scale_render_image);
+
+        if self.reactive {
+            app.insert_resource(WinitSettings::desktop_app())
+                .add_system(force_redraw_on_resize);
+        }
+    }
+}
+
+fn force_redraw_on_resize(
+    mut resize_events: EventReader<WindowResized>,
+    mut winit_settings: ResMut<WinitSettings>,
+) {
+    if resize_events.iter().next().is_some() {
+        winit_settings.focused_mode = UpdateMode::Continuous;
+        winit_settings.unfocused_mode = UpdateMode::Continuous;
+    } else {
+        winit_settings.focused_mode = UpdateMode::Reactive {
+            max_wait: Duration::from_secs(5),
+        };
+        winit_settings.unfocused_mode = UpdateMode::ReactiveLowPower {
+            max_wait: Duration::from_secs(60),
+        };
+    }
+}
@@ -1,13 +1,53 @@
 use bevy::core_pipeline::clear_color::ClearColorConfig;
-use bevy::render::camera::{RenderTarget, Viewport};
+use bevy::render::camera::{ManualTextureViewHandle, RenderTarget, ScalingMode, Viewport};
 use bevy::render::render_resource::{
     Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
 };
 use bevy::render::texture::ImageSampler;
 use bevy::render::view::RenderLayers;
+use bevy::sprite::Material2d;
 use bevy::window::PrimaryWindow;
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 
+/// A [`Material2d`] that can be used on a [`TexturePixelCamera`]'s upscale quad. Swap
+/// the default `ColorMaterial` for a custom implementation to run CRT curvature,
+/// scanline, or palette-quantization shaders on the upscaled output.
+pub trait PixelPostProcessMaterial: Material2d {
+    /// Build an instance of this material that samples `texture` (the low-res render
+    /// target) and is sized for a `canvas_size` canvas, so shaders can compute texel
+    /// coordinates.
+    fn from_canvas(texture: Handle<Image>, canvas_size: Vec2) -> Self;
+}
+
+impl PixelPostProcessMaterial for ColorMaterial {
+    fn from_canvas(texture: Handle<Image>, _canvas_size: Vec2) -> Self {
+        ColorMaterial {
+            texture: Some(texture),
+            ..Default::default()
+        }
+    }
+}
+
+/// The policy used to fit the virtual canvas into the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Scale the canvas as close to the window size as possible while keeping its
+    /// aspect ratio, using a fractional scale factor. This is the original behavior.
+    Fit,
+    /// Only ever scale the canvas by whole numbers, so every source pixel ends up the
+    /// same size on screen (no shimmer). Leftover space is filled with
+    /// `letterbox_color` bars. `allow_zero` controls whether the scale is allowed to
+    /// drop to 0 when the window is smaller than the canvas, instead of being clamped
+    /// to a minimum of 1.
+    Integer { allow_zero: bool },
+}
+
+impl Default for ScaleMode {
+    fn default() -> Self {
+        ScaleMode::Fit
+    }
+}
+
 /// This is for cameras that you want things to render to a texture then be scaled.
 /// size is the size of the virtual canvas and fixed is whether or not to let it grow in a certain direction.
 /// Ie a fixed height camera but is allowed to scale horizontally would go like fixed_axis: Some(false). the bool is for which axis. false being its fixed vertically true being fixed horizontally
@@ -16,17 +56,48 @@ pub struct TexturePixelCamera {
     pub size: UVec2,
     pub fixed_axis: Option<bool>,
     pub clear_color: Color,
+    /// How the virtual canvas is fit into the window.
+    pub scale_mode: ScaleMode,
+    /// Color used to fill the letterbox/pillarbox bars when `scale_mode` leaves
+    /// leftover space around the canvas. Only the first `TexturePixelCamera` to be set
+    /// up clears the window, so with several cameras its color fills every camera's
+    /// bars and the others' `letterbox_color` is unused.
+    pub letterbox_color: Color,
+    /// Normalized (0..1) sub-rectangle of the window this camera is drawn into.
+    /// `None` uses the whole window. Use this to place multiple `TexturePixelCamera`s
+    /// side by side for split-screen, or in a corner for a minimap.
+    pub target_rect: Option<Rect>,
+    /// Format of the intermediate render texture. Defaults to `Bgra8UnormSrgb`; use
+    /// e.g. `Rgba16Float` for HDR pixel art with tonemapping.
+    pub texture_format: TextureFormat,
+    /// Extra usages ORed onto the intermediate render texture's required
+    /// `TEXTURE_BINDING | COPY_DST | RENDER_ATTACHMENT`, e.g. `STORAGE_BINDING` to feed
+    /// the low-res target into a compute shader before upscale.
+    pub extra_usages: TextureUsages,
+    /// Render directly into an externally-owned [`ManualTextureViewHandle`] (an OpenXR
+    /// swapchain image, an embedding host surface, a video capture target, ...) instead
+    /// of allocating an internal render texture. When set, `setup_camera` skips the
+    /// internal image and the upscale quad/final-camera machinery entirely and points
+    /// this camera's target straight at the provided view, fitting `size` and
+    /// `fixed_axis` onto it via the camera's `OrthographicProjection` `scaling_mode`
+    /// (see [`TexturePixelCamera::fixed_axis`]) instead of the usual upscale quad.
+    pub external_target: Option<ManualTextureViewHandle>,
     init: bool,
 }
 
+/// Tags the quad that displays a [`TexturePixelCamera`]'s render target, pointing back
+/// at the source camera entity so `scale_render_image` can match them up when several
+/// `TexturePixelCamera`s exist at once.
 #[derive(Component)]
-pub struct RenderImage;
+pub struct RenderImage(pub Entity);
 
 #[derive(Component)]
 pub struct CameraTag;
 
+/// Tags the camera that draws a [`TexturePixelCamera`]'s upscaled quad to the window,
+/// pointing back at the source camera entity. See [`RenderImage`].
 #[derive(Component)]
-pub struct FinalCameraTag;
+pub struct FinalCameraTag(pub Entity);
 
 impl Default for TexturePixelCamera {
     fn default() -> Self {
@@ -34,6 +105,12 @@ impl Default for TexturePixelCamera {
             size: UVec2::new(256, 224),
             fixed_axis: None,
             clear_color: Color::WHITE,
+            scale_mode: ScaleMode::Fit,
+            letterbox_color: Color::BLACK,
+            target_rect: None,
+            texture_format: TextureFormat::Bgra8UnormSrgb,
+            extra_usages: TextureUsages::empty(),
+            external_target: None,
             init: false,
         }
     }
@@ -45,6 +122,12 @@ impl TexturePixelCamera {
             size,
             fixed_axis: axis,
             clear_color,
+            scale_mode: ScaleMode::Fit,
+            letterbox_color: Color::BLACK,
+            target_rect: None,
+            texture_format: TextureFormat::Bgra8UnormSrgb,
+            extra_usages: TextureUsages::empty(),
+            external_target: None,
             init: false,
         }
     }
@@ -54,6 +137,12 @@ impl TexturePixelCamera {
             size: UVec2::new(0, height),
             fixed_axis: Some(false),
             clear_color: Color::WHITE,
+            scale_mode: ScaleMode::Fit,
+            letterbox_color: Color::BLACK,
+            target_rect: None,
+            texture_format: TextureFormat::Bgra8UnormSrgb,
+            extra_usages: TextureUsages::empty(),
+            external_target: None,
             init: false,
         }
     }
@@ -62,6 +151,12 @@ impl TexturePixelCamera {
             size: UVec2::new(width, 0),
             fixed_axis: Some(true),
             clear_color: Color::WHITE,
+            scale_mode: ScaleMode::Fit,
+            letterbox_color: Color::BLACK,
+            target_rect: None,
+            texture_format: TextureFormat::Bgra8UnormSrgb,
+            extra_usages: TextureUsages::empty(),
+            external_target: None,
             init: false,
         }
     }
@@ -70,21 +165,93 @@ impl TexturePixelCamera {
             size: UVec2::new(width, height),
             fixed_axis: None,
             clear_color: Color::WHITE,
+            scale_mode: ScaleMode::Fit,
+            letterbox_color: Color::BLACK,
+            target_rect: None,
+            texture_format: TextureFormat::Bgra8UnormSrgb,
+            extra_usages: TextureUsages::empty(),
+            external_target: None,
             init: false,
         }
     }
+
+    /// Same as [`TexturePixelCamera::from_size`] but pinned to [`ScaleMode::Integer`]
+    /// for a pixel-perfect, integer-only upscale with letterbox bars.
+    pub fn from_size_integer_scaled(width: u32, height: u32, allow_zero: bool) -> Self {
+        Self {
+            size: UVec2::new(width, height),
+            fixed_axis: None,
+            clear_color: Color::WHITE,
+            scale_mode: ScaleMode::Integer { allow_zero },
+            letterbox_color: Color::BLACK,
+            target_rect: None,
+            texture_format: TextureFormat::Bgra8UnormSrgb,
+            extra_usages: TextureUsages::empty(),
+            external_target: None,
+            init: false,
+        }
+    }
+
+    /// Places this camera into a normalized (0..1) sub-rectangle of the window, for
+    /// split-screen or minimap layouts. See [`TexturePixelCamera::target_rect`].
+    pub fn with_target_rect(mut self, target_rect: Rect) -> Self {
+        self.target_rect = Some(target_rect);
+        self
+    }
+
+    /// Renders straight into an externally-owned `view`, skipping the internal render
+    /// texture and upscale quad. See [`TexturePixelCamera::external_target`].
+    pub fn from_external_target(
+        size: UVec2,
+        axis: Option<bool>,
+        view: ManualTextureViewHandle,
+    ) -> Self {
+        Self {
+            size,
+            fixed_axis: axis,
+            external_target: Some(view),
+            ..Self::default()
+        }
+    }
 }
 
-pub fn setup_camera(
+pub fn setup_camera<M: PixelPostProcessMaterial>(
     mut commands: Commands,
-    mut camera: Query<(&mut TexturePixelCamera, &mut Camera, &mut Camera2d, Entity)>,
+    mut camera: Query<(
+        &mut TexturePixelCamera,
+        &mut Camera,
+        &mut Camera2d,
+        &mut OrthographicProjection,
+        Entity,
+    )>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut materials: ResMut<Assets<M>>,
     mut images: ResMut<Assets<Image>>,
+    mut layer_count: Local<u8>,
+    mut final_camera_count: Local<u32>,
 ) {
-    for (mut pixel_camera, mut camera, mut camera_2d, entity) in camera.iter_mut() {
+    for (mut pixel_camera, mut camera, mut camera_2d, mut projection, entity) in camera.iter_mut()
+    {
         if !pixel_camera.init {
             pixel_camera.init = true;
+
+            if let Some(view_handle) = pixel_camera.external_target {
+                // Externally-owned target (OpenXR swapchain, embedding host surface,
+                // video capture, ...): just point this camera at it and skip the
+                // internal render texture and upscale quad/final-camera machinery.
+                // The view may not be `size` pixels, so pin the projection to `size`
+                // (and `fixed_axis`) ourselves rather than relying on its native
+                // resolution.
+                camera.target = RenderTarget::TextureView(view_handle);
+                camera_2d.clear_color = ClearColorConfig::Custom(pixel_camera.clear_color);
+                projection.scaling_mode = match pixel_camera.fixed_axis {
+                    Some(true) => ScalingMode::FixedHorizontal(pixel_camera.size.x as f32),
+                    _ => ScalingMode::FixedVertical(pixel_camera.size.y as f32),
+                };
+                commands.entity(entity).insert(CameraTag);
+                continue;
+            }
+
             let size = Extent3d {
                 width: pixel_camera.size.x,
                 height: pixel_camera.size.y,
@@ -97,12 +264,13 @@ pub fn setup_camera(
                     label: None,
                     size,
                     dimension: TextureDimension::D2,
-                    format: TextureFormat::Bgra8UnormSrgb,
+                    format: pixel_camera.texture_format,
                     mip_level_count: 1,
                     sample_count: 1,
                     usage: TextureUsages::TEXTURE_BINDING
                         | TextureUsages::COPY_DST
-                        | TextureUsages::RENDER_ATTACHMENT,
+                        | TextureUsages::RENDER_ATTACHMENT
+                        | pixel_camera.extra_usages,
                     view_formats: &[],
                 },
                 sampler_descriptor: ImageSampler::nearest(),
@@ -122,28 +290,43 @@ pub fn setup_camera(
                 .entity(entity)
                 .insert((CameraTag, UiCameraConfig { show_ui: false }));
 
-            let render_layer = RenderLayers::layer((RenderLayers::TOTAL_LAYERS - 1) as u8);
+            // Each TexturePixelCamera's quad/final-camera pair gets its own render
+            // layer so a final camera only ever draws its own quad, not every other
+            // pixel camera's. Layer 0 is left for the normal scene, so cycle through
+            // the remaining `TOTAL_LAYERS - 1` layers rather than growing forever.
+            let high_layer_slots = (RenderLayers::TOTAL_LAYERS - 1) as u8;
+            let layer_index = *layer_count % high_layer_slots;
+            let render_layer = RenderLayers::layer((RenderLayers::TOTAL_LAYERS - 1) as u8 - layer_index);
+            *layer_count = layer_count.wrapping_add(1);
 
-            let quad_handle = meshes.add(Mesh::from(shape::Quad::new(Vec2::new(
-                size.width as f32,
-                size.height as f32,
-            ))));
+            let canvas_size = Vec2::new(size.width as f32, size.height as f32);
+            let quad_handle = meshes.add(Mesh::from(shape::Quad::new(canvas_size)));
 
             // commands.entity(entity).insert((
             commands.spawn((
                 MaterialMesh2dBundle {
                     mesh: quad_handle.into(),
-                    material: materials.add(ColorMaterial {
-                        texture: Some(image_handle),
-                        ..Default::default()
-                    }),
+                    material: materials.add(M::from_canvas(image_handle, canvas_size)),
                     transform: Transform { ..default() },
                     ..default()
                 },
                 render_layer,
-                RenderImage,
+                RenderImage(entity),
             ));
 
+            // Only the first final camera clears the window (to `letterbox_color`); the
+            // rest leave it alone, otherwise each one's Clear load-op would wipe every
+            // earlier camera's quad. Each also needs its own `order` after the first
+            // main camera (0), both to render in a defined sequence and to avoid
+            // Bevy's camera-order ambiguity warning for cameras sharing a target.
+            let final_camera_index = *final_camera_count;
+            *final_camera_count += 1;
+            let letterbox_clear_color = if final_camera_index == 0 {
+                ClearColorConfig::Custom(pixel_camera.letterbox_color)
+            } else {
+                ClearColorConfig::None
+            };
+
             let final_camera = commands
                 .spawn((
                     Camera2dBundle {
@@ -156,13 +339,16 @@ pub fn setup_camera(
                                 ..Default::default()
                             }),
                             // renders after the first main camera which has default value: 0.
-                            order: 1,
+                            order: (1 + final_camera_index) as _,
                             ..default()
                         },
+                        camera_2d: Camera2d {
+                            clear_color: letterbox_clear_color,
+                        },
                         ..Camera2dBundle::default()
                     },
                     render_layer,
-                    FinalCameraTag,
+                    FinalCameraTag(entity),
                 ))
                 .id();
 
@@ -171,69 +357,110 @@ pub fn setup_camera(
     }
 }
 
+/// Fits `canvas` into `area` (both in pixels) according to `scale_mode`, returning the
+/// per-axis scale to apply to the render quad, and the resulting viewport size/position
+/// (position relative to the top-left of `area`).
+fn fit_viewport(scale_mode: ScaleMode, canvas: UVec2, area: UVec2) -> (Vec2, UVec2, UVec2) {
+    let (screen_width, screen_height) = (canvas.x, canvas.y);
+    match scale_mode {
+        ScaleMode::Fit => {
+            let aspect_ratio = screen_width as f32 / screen_height as f32;
+            let area_size: UVec2 = if area.y as f32 * aspect_ratio > area.x as f32
+            {
+                UVec2 {
+                    x: area.x,
+                    y: (area.x as f32 / aspect_ratio).floor() as u32,
+                }
+            } else {
+                UVec2 {
+                    x: (area.y as f32 * aspect_ratio).floor() as u32,
+                    y: area.y,
+                }
+            };
+
+            let scale = Vec2 {
+                x: area_size.x as f32 / screen_width as f32,
+                y: area_size.y as f32 / screen_height as f32,
+            };
+
+            let position: UVec2 = if area.y as f32 * aspect_ratio > area.x as f32
+            {
+                UVec2 {
+                    x: 0,
+                    y: (area.y / 2).checked_sub(area_size.y / 2).unwrap_or(0),
+                }
+            } else {
+                UVec2 {
+                    x: (area.x / 2).checked_sub(area_size.x / 2).unwrap_or(0),
+                    y: 0,
+                }
+            };
+
+            (scale, area_size, position)
+        }
+        ScaleMode::Integer { allow_zero } => {
+            let scale_x = area.x as f32 / screen_width as f32;
+            let scale_y = area.y as f32 / screen_height as f32;
+            let mut scale = scale_x.min(scale_y).floor();
+            if !allow_zero {
+                scale = scale.max(1.0);
+            }
+            scale = scale.max(0.0);
+
+            // A zero-size Viewport is rejected by wgpu, so even when `allow_zero` lets
+            // the canvas shrink to nothing visually (scale == 0), the emitted viewport
+            // itself is clamped to a 1px minimum.
+            let area_size = UVec2 {
+                x: (screen_width as f32 * scale) as u32,
+                y: (screen_height as f32 * scale) as u32,
+            }
+            .max(UVec2::ONE);
+
+            let position = UVec2 {
+                x: (area.x / 2).checked_sub(area_size.x / 2).unwrap_or(0),
+                y: (area.y / 2).checked_sub(area_size.y / 2).unwrap_or(0),
+            };
+
+            (Vec2::splat(scale), area_size, position)
+        }
+    }
+}
+
 pub fn scale_render_image(
-    mut texture_query: Query<&mut Transform, With<RenderImage>>,
-    mut camera_query: Query<&mut bevy::render::camera::Camera, With<FinalCameraTag>>,
-    mut pixel_camera_query: Query<&TexturePixelCamera, With<CameraTag>>,
-    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut texture_query: Query<(&RenderImage, &mut Transform)>,
+    mut camera_query: Query<(&FinalCameraTag, &mut bevy::render::camera::Camera)>,
+    pixel_camera_query: Query<(Entity, &TexturePixelCamera), With<CameraTag>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
 ) {
-    if let Ok(mut texture_transform) = texture_query.get_single_mut() {
-        if let Ok(window) = windows.get_single_mut() {
-            if let Ok(mut camera) = camera_query.get_single_mut() {
-                if let Ok(pixel_camera) = pixel_camera_query.get_single_mut() {
-                    let (screen_width, screen_height) = (pixel_camera.size.x, pixel_camera.size.y);
-                    let aspect_ratio = screen_width as f32 / screen_height as f32;
-                    let window_size: UVec2 = if window.physical_height() > window.physical_width()
-                        || window.physical_height() as f32 * aspect_ratio
-                            > window.physical_width() as f32
-                    {
-                        UVec2 {
-                            x: window.physical_width(),
-                            y: (window.physical_width() as f32 / aspect_ratio).floor() as u32,
-                        }
-                    } else {
-                        UVec2 {
-                            x: (window.physical_height() as f32 * aspect_ratio).floor() as u32,
-                            y: window.physical_height(),
-                        }
-                    };
-
-                    let scale_width = window_size.x as f32 / screen_width as f32;
-                    let scale_height = window_size.y as f32 / screen_height as f32;
-                    let window_position: UVec2 = if window.physical_height()
-                        > window.physical_width()
-                        || window.physical_height() as f32 * aspect_ratio
-                            > window.physical_width() as f32
-                    {
-                        if let Some(height) =
-                            (window.physical_height() / 2).checked_sub(window_size.y / 2)
-                        {
-                            UVec2 { x: 0, y: height }
-                        } else {
-                            UVec2::ZERO
-                        }
-                    } else {
-                        if let Some(width) =
-                            (window.physical_width() / 2).checked_sub(window_size.x / 2)
-                        {
-                            UVec2 { x: width, y: 0 }
-                        } else {
-                            UVec2::ZERO
-                        }
-                    };
-
-                    texture_transform.scale = Vec3 {
-                        x: scale_width as f32,
-                        y: scale_height as f32,
-                        z: 1.0,
-                    };
-
-                    camera.viewport = Some(Viewport {
-                        physical_size: window_size,
-                        physical_position: window_position,
-                        ..Default::default()
-                    });
-                }
+    if let Ok(window) = windows.get_single() {
+        let window_size = Vec2::new(window.physical_width() as f32, window.physical_height() as f32);
+
+        for (entity, pixel_camera) in pixel_camera_query.iter() {
+            let (area_position, area_size) = match pixel_camera.target_rect {
+                Some(rect) => (
+                    (rect.min * window_size).as_uvec2(),
+                    (rect.size() * window_size).as_uvec2(),
+                ),
+                None => (UVec2::ZERO, window_size.as_uvec2()),
+            };
+
+            let (scale, viewport_size, viewport_position) =
+                fit_viewport(pixel_camera.scale_mode, pixel_camera.size, area_size);
+
+            if let Some((_, mut texture_transform)) =
+                texture_query.iter_mut().find(|(tag, _)| tag.0 == entity)
+            {
+                texture_transform.scale = scale.extend(1.0);
+            }
+
+            if let Some((_, mut camera)) =
+                camera_query.iter_mut().find(|(tag, _)| tag.0 == entity)
+            {
+                camera.viewport = Some(Viewport {
+                    physical_size: viewport_size,
+                    physical_position: area_position + viewport_position,
+                    ..Default::default()
+                });
             }
         }
     }